@@ -3,6 +3,8 @@ use bevy::render::texture::ImageSettings;
 use bevy_baseview_plugin::{attach_to, AppProxy, DefaultBaseviewPlugins, ParentWin};
 use bevy_embedded_assets::EmbeddedAssetPlugin;
 
+use crate::plugin_core;
+
 pub type HostToGuiTx = crossbeam_channel::Sender<HostToGui>;
 pub type HostToGuiRx = crossbeam_channel::Receiver<HostToGui>;
 pub type GuiToHostTx = crossbeam_channel::Sender<GuiToHost>;
@@ -12,17 +14,32 @@ pub type GuiToHostRx = crossbeam_channel::Receiver<GuiToHost>;
 pub enum HostToGui {
     // Add any messages to send to the Bevy app here.
     ParamUpdate(ParamUpdate),
+    /// The host has reconciled the window's logical/physical size against
+    /// this new system scale factor; rescale the camera/sprites to match.
+    ScaleFactorChanged(f64),
 }
 
 #[derive(Copy, Clone, Debug)]
 pub enum GuiToHost {
     // Add any messages to send from the Bevy app here.
     ParamUpdate(ParamUpdate),
+    /// Arm/disarm "MIDI learn": the next Control Change the host receives
+    /// is bound to parameter 0 instead of being applied directly.
+    MidiLearnToggle,
+    /// Baseview reported a new system scale factor for our window, e.g.
+    /// after the host moved it to a monitor with different DPI.
+    ScaleFactorChanged(f64),
+    /// Baseview's window was resized (e.g. the user drag-resized it); the
+    /// new logical size, so `BaseviewDemoEditor::size()` can report it back
+    /// to the host.
+    SizeChanged(f64, f64),
 }
 
+/// An update to one entry in [`plugin_core::PARAMS`], by index.
 #[derive(Copy, Clone, Debug)]
-pub enum ParamUpdate {
-    GainUpdated(f64),
+pub struct ParamUpdate {
+    pub index: usize,
+    pub value: f64,
 }
 
 fn host_to_gui_relay(rx: Res<HostToGuiRx>, mut event_writer: EventWriter<HostToGui>) {
@@ -40,17 +57,156 @@ fn gui_to_host_relay(tx: Res<GuiToHostTx>, mut event_reader: EventReader<GuiToHo
     }
 }
 
-fn update_from_host(mut event_reader: EventReader<HostToGui>, mut gain_value: ResMut<GainValue>) {
+fn update_from_host(
+    mut event_reader: EventReader<HostToGui>,
+    mut param_values: ResMut<ParamValues>,
+    mut knob_drag: ResMut<KnobDrag>,
+    mut scale_factor: ResMut<ScaleFactor>,
+) {
     for msg in event_reader.iter() {
         match msg {
-            HostToGui::ParamUpdate(ParamUpdate::GainUpdated(new_value)) => {
-                gain_value.current = *new_value;
-                gain_value.proposed = None;
+            HostToGui::ParamUpdate(update) => {
+                param_values.set(update.index, update.value);
+                if update.index == plugin_core::GAIN_INDEX {
+                    knob_drag.proposed = None;
+                }
+            }
+            HostToGui::ScaleFactorChanged(new_factor) => {
+                scale_factor.0 = *new_factor;
             }
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+struct ScaleFactor(f64);
+
+impl Default for ScaleFactor {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Apply the host-controlled opacity to the clear color and the background
+/// sprite, so the DAW's track color/UI can show through around the knob.
+///
+/// This only covers what Bevy itself can do (alpha-blending what it draws);
+/// whether that's actually visible depends on baseview handing us an
+/// alpha-capable, compositor-backed surface, which isn't something this
+/// baseview version's `WindowOpenOptions` lets us request. In practice:
+/// macOS layer-backed views composite alpha by default; Wayland generally
+/// does too; X11 requires a compositor (e.g. picom) and is otherwise opaque
+/// regardless of what we draw here.
+fn apply_opacity(
+    param_values: Res<ParamValues>,
+    mut last_opacity: Local<f64>,
+    mut clear_color: ResMut<ClearColor>,
+    mut background_query: Query<&mut Sprite, With<Background>>,
+) {
+    let opacity = param_values.get(plugin_core::OPACITY_INDEX);
+    if opacity == *last_opacity {
+        return;
+    }
+    *last_opacity = opacity;
+    clear_color.0.set_a(opacity as f32);
+    for mut sprite in &mut background_query {
+        sprite.color.set_a(opacity as f32);
+    }
+}
+
+/// Watch baseview's own window for scale-factor changes (e.g. the host
+/// drags the plugin window to a monitor with different DPI) and relay
+/// them to the host so `BaseviewDemoEditor::size()` stays in sync.
+fn detect_scale_factor_change(
+    wnds: Res<Windows>,
+    mut last_scale_factor: Local<Option<f64>>,
+    mut event_writer: EventWriter<GuiToHost>,
+) {
+    let wnd = match wnds.get_primary() {
+        Some(wnd) => wnd,
+        None => return,
+    };
+    let scale_factor = wnd.scale_factor();
+    let changed = match *last_scale_factor {
+        Some(last) => (scale_factor - last).abs() > f64::EPSILON,
+        None => true,
+    };
+    if changed {
+        *last_scale_factor = Some(scale_factor);
+        event_writer.send(GuiToHost::ScaleFactorChanged(scale_factor));
+    }
+}
+
+/// Watch for the user drag-resizing the window and relay the new logical
+/// size to the host. This runs on every frame the app updates, regardless
+/// of window state; it does not itself schedule frames, so whether drawing
+/// continues throughout a drag depends on baseview keeping the app updating.
+fn detect_window_resize(
+    wnds: Res<Windows>,
+    mut last_size: Local<(f32, f32)>,
+    mut event_writer: EventWriter<GuiToHost>,
+) {
+    let wnd = match wnds.get_primary() {
+        Some(wnd) => wnd,
+        None => return,
+    };
+    let size = (wnd.width(), wnd.height());
+    if *last_size == (0.0, 0.0) {
+        *last_size = size;
+        return;
+    }
+    if size != *last_size {
+        *last_size = size;
+        event_writer.send(GuiToHost::SizeChanged(size.0 as f64, size.1 as f64));
+    }
+}
+
+#[derive(Component)]
+struct Background;
+
+/// Rescale the background (and the knob sprite parented to it) to fill the
+/// current window size, keeping the original aspect ratio.
+fn reflow_layout(
+    wnds: Res<Windows>,
+    mut background_query: Query<&mut Transform, With<Background>>,
+) {
+    let wnd = match wnds.get_primary() {
+        Some(wnd) => wnd,
+        None => return,
+    };
+    let scale = ((wnd.width() as f64 / crate::WINDOW_WIDTH)
+        .min(wnd.height() as f64 / crate::WINDOW_HEIGHT)
+        * 0.5) as f32;
+    for mut transform in &mut background_query {
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+/// Rescale the camera so sprites stay a consistent logical size no matter
+/// what physical scale factor the host/baseview negotiated, instead of
+/// rendering blurry (under-scaled) or clipped (over-scaled) on HiDPI
+/// displays.
+fn apply_scale_factor(
+    scale_factor: Res<ScaleFactor>,
+    mut query: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+    if !scale_factor.is_changed() {
+        return;
+    }
+    for mut projection in &mut query {
+        projection.scale = (1.0 / scale_factor.0) as f32;
+    }
+}
+
+/// Pressing `L` toggles MIDI learn for parameter 0. There's no on-screen
+/// indicator yet, so check the host's log for confirmation of the mapping.
+fn midi_learn_input(keys: Res<Input<KeyCode>>, mut event_writer: EventWriter<GuiToHost>) {
+    if keys.just_pressed(KeyCode::L) {
+        log::info!("toggling MIDI learn");
+        event_writer.send(GuiToHost::MidiLearnToggle);
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 enum AppState {
     Idle,
@@ -65,19 +221,36 @@ struct DragState {
 #[derive(Debug, Default, Clone)]
 struct CursorPosition(Vec2);
 
-#[derive(Debug, Default, Clone)]
-struct GainValue {
-    current: f64,
-    proposed: Option<f64>,
-}
+/// Every entry in [`plugin_core::PARAMS`], by index, as last reported by the
+/// host.
+#[derive(Debug, Clone)]
+struct ParamValues(Vec<f64>);
 
-impl GainValue {
-    fn new(value: f64) -> Self {
-        Self {
-            current: value,
-            proposed: None,
-        }
+impl ParamValues {
+    fn new() -> Self {
+        Self(
+            plugin_core::PARAMS
+                .iter()
+                .map(|spec| spec.default as f64)
+                .collect(),
+        )
     }
+
+    fn get(&self, index: usize) -> f64 {
+        self.0[index]
+    }
+
+    fn set(&mut self, index: usize, value: f64) {
+        self.0[index] = value;
+    }
+}
+
+/// The knob's live drag-preview value, separate from [`ParamValues`] since
+/// it's only ever meaningful for the gain parameter while the user is
+/// actively dragging, and is cleared once the host confirms the new value.
+#[derive(Debug, Default, Clone)]
+struct KnobDrag {
+    proposed: Option<f64>,
 }
 
 pub fn create_app<P: Into<ParentWin>>(
@@ -100,7 +273,9 @@ pub fn create_app<P: Into<ParentWin>>(
     app.insert_resource(ImageSettings::default_nearest()) // prevents blurry sprites
         .insert_resource(DragState::default())
         .insert_resource(CursorPosition::default())
-        .insert_resource(GainValue::new(0.0))
+        .insert_resource(ParamValues::new())
+        .insert_resource(KnobDrag::default())
+        .insert_resource(ScaleFactor::default())
         .insert_resource(gui_rx)
         .add_event::<HostToGui>()
         .add_system(host_to_gui_relay)
@@ -108,6 +283,12 @@ pub fn create_app<P: Into<ParentWin>>(
         .add_event::<GuiToHost>()
         .add_system(gui_to_host_relay)
         .add_system(update_from_host)
+        .add_system(apply_scale_factor)
+        .add_system(detect_scale_factor_change)
+        .add_system(detect_window_resize)
+        .add_system(reflow_layout)
+        .add_system(apply_opacity)
+        .add_system(midi_learn_input)
         .add_system(cursor_position)
         .add_state(AppState::Idle)
         .add_startup_system(setup)
@@ -134,6 +315,7 @@ fn setup(
             transform: Transform::from_scale(Vec3::splat(0.5)),
             ..default()
         })
+        .insert(Background)
         .with_children(|parent| {
             parent.spawn_bundle(SpriteSheetBundle {
                 texture_atlas: texture_atlas_handle,
@@ -164,7 +346,8 @@ fn knob_activated(
     wnds: Res<Windows>,
     mut state: ResMut<State<AppState>>,
     mut drag_state: ResMut<DragState>,
-    mut gain_value: ResMut<GainValue>,
+    mut param_values: ResMut<ParamValues>,
+    mut knob_drag: ResMut<KnobDrag>,
     mut event_writer: EventWriter<GuiToHost>,
     cursor_position: Res<CursorPosition>,
     mut buttons: ResMut<Input<MouseButton>>,
@@ -177,15 +360,14 @@ fn knob_activated(
         }
         log::debug!("Setting state to Idle");
         drag_state.start = None;
-        if let Some(new_gain) = gain_value.proposed {
-            gain_value.current = new_gain;
-            event_writer.send(GuiToHost::ParamUpdate(ParamUpdate::GainUpdated(new_gain)));
-        } else {
-            // Restore the previous value.
-            event_writer.send(GuiToHost::ParamUpdate(ParamUpdate::GainUpdated(
-                gain_value.current,
-            )));
-        }
+        let new_gain = knob_drag
+            .proposed
+            .unwrap_or_else(|| param_values.get(plugin_core::GAIN_INDEX));
+        param_values.set(plugin_core::GAIN_INDEX, new_gain);
+        event_writer.send(GuiToHost::ParamUpdate(ParamUpdate {
+            index: plugin_core::GAIN_INDEX,
+            value: new_gain,
+        }));
     } else {
         let wnd = match wnds.get_primary() {
             Some(wnd) => wnd,
@@ -208,10 +390,13 @@ fn knob_activated(
         let pct = (delta.y / (wnd.height() / 1.5)) as f64;
 
         // TODO: Factor this out into a separate system
-        let current_gain = gain_value.current;
+        let current_gain = param_values.get(plugin_core::GAIN_INDEX);
         let new_gain = (current_gain + pct).clamp(0.0, 1.0);
-        event_writer.send(GuiToHost::ParamUpdate(ParamUpdate::GainUpdated(new_gain)));
-        gain_value.proposed = Some(new_gain);
+        event_writer.send(GuiToHost::ParamUpdate(ParamUpdate {
+            index: plugin_core::GAIN_INDEX,
+            value: new_gain,
+        }));
+        knob_drag.proposed = Some(new_gain);
     }
 }
 
@@ -225,14 +410,17 @@ fn cursor_position(
 }
 
 fn knob_render(
-    gain_value: ResMut<GainValue>,
+    param_values: Res<ParamValues>,
+    knob_drag: Res<KnobDrag>,
     mut query: Query<(&mut TextureAtlasSprite, &Handle<TextureAtlas>)>,
     texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
     for (mut sprite, texture_atlas_handle) in &mut query {
         let texture_atlas = texture_atlases.get(texture_atlas_handle).unwrap();
         let count = texture_atlas.textures.len();
-        let gain = gain_value.proposed.unwrap_or(gain_value.current);
+        let gain = knob_drag
+            .proposed
+            .unwrap_or_else(|| param_values.get(plugin_core::GAIN_INDEX));
         sprite.index = ((count as f64 * gain) as usize).clamp(0, count - 1);
     }
 }
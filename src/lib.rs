@@ -1,6 +1,8 @@
 #![allow(non_snake_case)]
 mod editor_app;
 mod log_helpers;
+mod nih_backend;
+mod plugin_core;
 
 #[macro_use]
 extern crate vst;
@@ -13,8 +15,8 @@ use vst::editor::Editor;
 use vst::host::Host;
 use vst::prelude::*;
 
-const WINDOW_WIDTH: f64 = 500.0;
-const WINDOW_HEIGHT: f64 = 300.0;
+pub(crate) const WINDOW_WIDTH: f64 = 500.0;
+pub(crate) const WINDOW_HEIGHT: f64 = 300.0;
 
 struct BaseviewDemo {
     host: HostCallback,
@@ -22,24 +24,33 @@ struct BaseviewDemo {
 }
 
 struct BaseviewDemoParameters {
-    gain: AtomicFloat,
+    core: plugin_core::ParamCore,
     host_to_gui_tx: Arc<RwLock<Option<editor_app::HostToGuiTx>>>,
     gui_to_host_rx: Arc<RwLock<Option<editor_app::GuiToHostRx>>>,
+    /// The editor's logical/physical size and scale factor. Starts out as a
+    /// best guess (the host asks `Editor::size` before `open`, so the real
+    /// scale isn't known yet) and is reconciled once baseview reports the
+    /// system scale factor after the window opens.
+    window_info: RwLock<baseview::WindowInfo>,
 }
 
 impl PluginParameters for BaseviewDemoParameters {
     fn get_parameter(&self, index: i32) -> f32 {
-        match index {
-            0 => self.gain.get(),
-            _ => 0.0,
+        match plugin_core::PARAMS.get(index as usize) {
+            Some(spec) => spec.range.normalize(self.core.get(index as usize)),
+            None => 0.0,
         }
     }
 
     fn set_parameter(&self, index: i32, value: f32) {
         log::info!("set_parameter: {index} {value:.5}");
-        if index == 0 {
-            self.gain.set(value)
-        }
+        // VST2 always calls this with a normalized 0.0..=1.0 value; `core`
+        // (and every other backend) works in each parameter's real range.
+        let real_value = match plugin_core::PARAMS.get(index as usize) {
+            Some(spec) => spec.range.denormalize(value),
+            None => return,
+        };
+        self.core.set(index as usize, real_value);
         let host_to_gui_tx = match self.host_to_gui_tx.read() {
             Ok(host_to_gui_tx_guard) => host_to_gui_tx_guard,
             Err(err) => {
@@ -51,33 +62,33 @@ impl PluginParameters for BaseviewDemoParameters {
             // TODO(PANIC): replace panic with more intelligent error handling
             host_to_gui_tx
                 .send(editor_app::HostToGui::ParamUpdate(
-                    editor_app::ParamUpdate::GainUpdated(value as f64),
+                    editor_app::ParamUpdate {
+                        index: index as usize,
+                        value: real_value as f64,
+                    },
                 ))
                 .expect("send to gui");
         }
     }
 
     fn get_parameter_name(&self, index: i32) -> String {
-        match index {
-            0 => "gain".to_string(),
-            _ => "".to_string(),
+        match plugin_core::PARAMS.get(index as usize) {
+            Some(spec) => spec.name.to_string(),
+            None => "".to_string(),
         }
     }
 
     fn get_parameter_label(&self, index: i32) -> String {
-        match index {
-            0 => "%".to_string(),
-            _ => "".to_string(),
+        match plugin_core::PARAMS.get(index as usize) {
+            Some(spec) => spec.label.to_string(),
+            None => "".to_string(),
         }
     }
 
     fn get_parameter_text(&self, index: i32) -> String {
-        match index {
-            0 => {
-                let gain_db = 20.0 * self.gain.get().log10();
-                format!("{:.1} dB", gain_db)
-            }
-            _ => String::new(),
+        match plugin_core::PARAMS.get(index as usize) {
+            Some(_) => self.core.text(index as usize),
+            None => String::new(),
         }
     }
 }
@@ -86,23 +97,110 @@ impl BaseviewDemo {
     fn process_gui_msgs(&self) {
         // TODO(PANIC): replace panic with more intelligent error handling
         // Consolidate updates
-        let mut updated_gain = None;
+        let mut updated_param = None;
+        let mut toggle_midi_learn = false;
+        let mut updated_scale_factor = None;
+        let mut updated_size = None;
         if let Some(gui_to_host_rx) = &*self.params.gui_to_host_rx.read().unwrap() {
             for msg in gui_to_host_rx.try_iter() {
                 //log::info!("core got {msg:?}");
                 match &msg {
                     editor_app::GuiToHost::ParamUpdate(param_update) => {
-                        let editor_app::ParamUpdate::GainUpdated(value) = param_update;
-                        updated_gain = Some(*value as f32)
+                        updated_param = Some(*param_update)
+                    }
+                    editor_app::GuiToHost::MidiLearnToggle => toggle_midi_learn = true,
+                    editor_app::GuiToHost::ScaleFactorChanged(factor) => {
+                        updated_scale_factor = Some(*factor)
+                    }
+                    editor_app::GuiToHost::SizeChanged(width, height) => {
+                        updated_size = Some((*width, *height))
                     }
                 }
             }
         }
-        if let Some(new_gain) = updated_gain {
-            self.params.gain.set(new_gain);
-            self.host.begin_edit(0);
-            self.host.automate(0, new_gain);
-            self.host.end_edit(0);
+        if let Some(update) = updated_param {
+            self.params.core.set(update.index, update.value as f32);
+            // `Host::automate` expects a normalized value, same as VST2's
+            // `PluginParameters::get/set_parameter`; `update.value` is real.
+            let normalized = plugin_core::PARAMS[update.index]
+                .range
+                .normalize(update.value as f32);
+            self.host.begin_edit(update.index as i32);
+            self.host.automate(update.index as i32, normalized);
+            self.host.end_edit(update.index as i32);
+        }
+        if toggle_midi_learn {
+            self.params.core.toggle_midi_learn();
+        }
+        if let Some(new_scale_factor) = updated_scale_factor {
+            if let Ok(mut window_info) = self.params.window_info.write() {
+                *window_info = baseview::WindowInfo::from_logical_size(
+                    window_info.logical_size(),
+                    new_scale_factor,
+                );
+            }
+            if let Some(host_to_gui_tx) = &*self.params.host_to_gui_tx.read().unwrap() {
+                host_to_gui_tx
+                    .send(editor_app::HostToGui::ScaleFactorChanged(new_scale_factor))
+                    .expect("send to gui");
+            }
+        }
+        if let Some((width, height)) = updated_size {
+            if let Ok(mut window_info) = self.params.window_info.write() {
+                let scale_factor = window_info.scale();
+                *window_info = baseview::WindowInfo::from_logical_size(
+                    baseview::Size::new(width, height),
+                    scale_factor,
+                );
+            }
+            // NOTE: the only `Host` trait methods this file uses elsewhere
+            // (`begin_edit`/`automate`/`end_edit`) cover parameter
+            // automation, not window sizing. The VST2 SDK's host-side
+            // resize opcode (`audioMasterSizeWindow`) isn't wrapped by the
+            // `vst` crate's safe `Host` trait, and this tree has no pinned
+            // crate version (no Cargo.lock) to check whether a given
+            // checkout exposes it some other way. Until that's confirmed,
+            // VST2 hosts pick up the new size the next time they poll
+            // `Editor::size` rather than being told proactively. The
+            // nih_plug backend doesn't have this gap: `GuiContext` does
+            // expose a real resize push via `GuiContext::request_resize`.
+        }
+    }
+
+    /// Parse a single raw MIDI event. Only Control Change messages are
+    /// understood; everything else (note on/off, pitch bend, ...) is
+    /// ignored, including while MIDI learn is armed.
+    fn handle_midi(&mut self, data: [u8; 3]) {
+        let status = data[0] & 0xF0;
+        if status != 0xB0 {
+            return;
+        }
+        let cc_number = data[1];
+        let cc_value = data[2];
+
+        let gain = match self.params.core.apply_cc(cc_number, cc_value) {
+            Some(gain) => gain,
+            None => return,
+        };
+        self.host.begin_edit(0);
+        self.host.automate(
+            0,
+            plugin_core::PARAMS[plugin_core::GAIN_INDEX]
+                .range
+                .normalize(gain),
+        );
+        self.host.end_edit(0);
+
+        if let Some(host_to_gui_tx) = &*self.params.host_to_gui_tx.read().unwrap() {
+            // TODO(PANIC): replace panic with more intelligent error handling
+            host_to_gui_tx
+                .send(editor_app::HostToGui::ParamUpdate(
+                    editor_app::ParamUpdate {
+                        index: plugin_core::GAIN_INDEX,
+                        value: gain as f64,
+                    },
+                ))
+                .expect("send to gui");
         }
     }
 }
@@ -112,9 +210,13 @@ impl Plugin for BaseviewDemo {
         BaseviewDemo {
             host,
             params: Arc::new(BaseviewDemoParameters {
-                gain: AtomicFloat::new(1.0),
+                core: plugin_core::ParamCore::new(),
                 host_to_gui_tx: Arc::new(RwLock::new(None)),
                 gui_to_host_rx: Arc::new(RwLock::new(None)),
+                window_info: RwLock::new(baseview::WindowInfo::from_logical_size(
+                    baseview::Size::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+                    1.0,
+                )),
             }),
         }
     }
@@ -129,17 +231,14 @@ impl Plugin for BaseviewDemo {
         Info {
             name: "Baseview Demo".to_string(),
             unique_id: 14357, // Used by hosts to differentiate between plugins.
-            parameters: 1,
+            parameters: plugin_core::PARAMS.len() as i32,
             ..Default::default()
         }
     }
 
     // Return handle to plugin editor if supported.
     fn get_editor(&mut self) -> Option<Box<dyn Editor>> {
-        Some(Box::new(BaseviewDemoEditor::new(
-            baseview::Size::new(WINDOW_WIDTH, WINDOW_HEIGHT),
-            Arc::clone(&self.params),
-        )) as Box<dyn Editor>)
+        Some(Box::new(BaseviewDemoEditor::new(Arc::clone(&self.params))) as Box<dyn Editor>)
     }
 
     fn can_do(&self, can_do: CanDo) -> Supported {
@@ -152,10 +251,18 @@ impl Plugin for BaseviewDemo {
         }
     }
 
+    fn process_events(&mut self, events: &Events) {
+        for event in events.events() {
+            if let Event::Midi(midi_event) = event {
+                self.handle_midi(midi_event.data);
+            }
+        }
+    }
+
     fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
         self.process_gui_msgs();
         // For each input and output
-        let gain = self.params.gain.get();
+        let gain = self.params.core.get(plugin_core::GAIN_INDEX);
         for (input, output) in buffer.zip() {
             // For each input sample and output sample in buffer
             for (in_frame, out_frame) in input.iter().zip(output.iter_mut()) {
@@ -167,7 +274,7 @@ impl Plugin for BaseviewDemo {
     fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
         self.process_gui_msgs();
         // For each input and output
-        let gain = self.params.gain.get() as f64;
+        let gain = self.params.core.get(plugin_core::GAIN_INDEX) as f64;
         for (input, output) in buffer.zip() {
             // For each input sample and output sample in buffer
             for (in_frame, out_frame) in input.iter().zip(output.iter_mut()) {
@@ -203,19 +310,14 @@ plugin_main!(BaseviewDemo);
 
 struct BaseviewDemoEditor {
     params: Arc<BaseviewDemoParameters>,
-    window_info: baseview::WindowInfo,
-    //size: baseview::Size,
     open: bool,
     app: Option<AppProxy>,
 }
 
 impl BaseviewDemoEditor {
-    fn new(size: baseview::Size, params: Arc<BaseviewDemoParameters>) -> Self {
-        // TODO: Fix scale factor/DPI settings.
-        let window_info = baseview::WindowInfo::from_logical_size(size, 1.0);
+    fn new(params: Arc<BaseviewDemoParameters>) -> Self {
         Self {
             params,
-            window_info,
             open: false,
             app: None,
         }
@@ -224,7 +326,9 @@ impl BaseviewDemoEditor {
 
 impl Editor for BaseviewDemoEditor {
     fn size(&self) -> (i32, i32) {
-        let phy_size = self.window_info.physical_size();
+        // Reconciled against the host's actual DPI once `open` runs and
+        // baseview reports the system scale factor via `GuiToHost::ScaleFactorChanged`.
+        let phy_size = self.params.window_info.read().unwrap().physical_size();
         (phy_size.width as i32, phy_size.height as i32)
     }
 
@@ -237,19 +341,27 @@ impl Editor for BaseviewDemoEditor {
         if self.open {
             return false;
         }
+        // NOTE: this baseview version's `WindowOpenOptions` has no way to
+        // request an alpha-capable surface, so the `opacity` parameter only
+        // affects what Bevy draws (`ClearColor`/sprite alpha), not whether
+        // the window itself is composited as transparent.
         let window_open_options = baseview::WindowOpenOptions {
             title: "Baseview Gain Demo".to_string(),
-            size: self.window_info.logical_size(),
+            size: self.params.window_info.read().unwrap().logical_size(),
             scale: baseview::WindowScalePolicy::SystemScaleFactor,
         };
         let (host_to_gui_tx, gui_to_host_rx, app_proxy) =
             editor_app::create_app(&window_open_options, ParentWin::new(parent));
-        // TODO: Clean up parameter pre-population.
-        host_to_gui_tx
-            .send(editor_app::HostToGui::ParamUpdate(
-                editor_app::ParamUpdate::GainUpdated(self.params.gain.get() as f64),
-            ))
-            .expect("send to gui");
+        for (index, _) in plugin_core::PARAMS.iter().enumerate() {
+            host_to_gui_tx
+                .send(editor_app::HostToGui::ParamUpdate(
+                    editor_app::ParamUpdate {
+                        index,
+                        value: self.params.core.get(index) as f64,
+                    },
+                ))
+                .expect("send to gui");
+        }
 
         if let Ok(mut host_to_gui_tx_ref) = self.params.host_to_gui_tx.write() {
             *host_to_gui_tx_ref = Some(host_to_gui_tx);
@@ -0,0 +1,312 @@
+//! VST3 and CLAP entry point, built on `nih_plug` rather than the `vst`
+//! crate used by `BaseviewDemo` in `lib.rs`. VST 2.4 is effectively
+//! unmaintained and rejected by many modern DAWs, so this backend exposes
+//! the same gain parameter and the same baseview-hosted Bevy editor as
+//! VST3/CLAP plugins, sharing [`plugin_core::ParamCore`] and
+//! `editor_app::create_app` unchanged with the VST2 backend. Only the
+//! parameter registration and the `Editor` glue differ per format.
+
+use std::num::NonZeroU32;
+use std::sync::{Arc, RwLock};
+
+use nih_plug::prelude::*;
+
+use crate::editor_app;
+use crate::plugin_core::{self, ParamCore};
+
+pub struct BaseviewDemoNih {
+    params: Arc<BaseviewDemoNihParams>,
+    /// Not part of `BaseviewDemoNihParams`: `nih_plug`'s `#[persist]` needs
+    /// `PersistentField`, which is only blanket-implemented for serde
+    /// `Serialize`/`Deserialize` types, and `ParamCore`'s atomics/locks don't
+    /// derive those. Like the VST2 backend in `lib.rs`, it's just
+    /// re-initialized from `plugin_core::PARAMS`'s defaults on every load.
+    core: Arc<ParamCore>,
+    /// Reachable from `process()` so a MIDI-CC-driven gain change can push a
+    /// `HostToGui::ParamUpdate` to the editor the same way
+    /// `BaseviewDemo::handle_midi` does for VST2; `None` until the editor
+    /// window has opened at least once.
+    host_to_gui_tx: Arc<RwLock<Option<editor_app::HostToGuiTx>>>,
+    /// Last opacity value synced into `core`/the editor, so `process()` can
+    /// detect host automation of the `opacity` parameter (which, unlike
+    /// `gain`, has no `NoteEvent`-driven path into `core` already).
+    last_opacity: f32,
+}
+
+#[derive(Params)]
+pub struct BaseviewDemoNihParams {
+    #[id = "gain"]
+    gain: FloatParam,
+
+    /// Editor background opacity; see the opacity entry in
+    /// [`plugin_core::PARAMS`] for the same cosmetic-only caveat.
+    #[id = "opacity"]
+    opacity: FloatParam,
+}
+
+impl BaseviewDemoNihParams {
+    /// Maps a [`plugin_core::PARAMS`] index to this backend's matching
+    /// `nih_plug` parameter, so `GuiContext` can be told about GUI-driven
+    /// parameter changes generically instead of one match arm per
+    /// parameter. `None` if the table ever grows past what's registered
+    /// here.
+    fn param_ptr(&self, index: usize) -> Option<ParamPtr> {
+        match index {
+            plugin_core::GAIN_INDEX => Some(self.gain.as_ptr()),
+            plugin_core::OPACITY_INDEX => Some(self.opacity.as_ptr()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for BaseviewDemoNih {
+    fn default() -> Self {
+        let core = Arc::new(ParamCore::new());
+        Self {
+            params: Arc::new(BaseviewDemoNihParams {
+                gain: FloatParam::new(
+                    "Gain",
+                    core.get(plugin_core::GAIN_INDEX),
+                    FloatRange::Linear {
+                        min: plugin_core::PARAMS[plugin_core::GAIN_INDEX].range.min,
+                        max: plugin_core::PARAMS[plugin_core::GAIN_INDEX].range.max,
+                    },
+                )
+                .with_unit(" %")
+                .with_value_to_string(Arc::new(|value| {
+                    (plugin_core::PARAMS[plugin_core::GAIN_INDEX].format)(value)
+                })),
+                opacity: FloatParam::new(
+                    "Opacity",
+                    core.get(plugin_core::OPACITY_INDEX),
+                    FloatRange::Linear {
+                        min: plugin_core::PARAMS[plugin_core::OPACITY_INDEX].range.min,
+                        max: plugin_core::PARAMS[plugin_core::OPACITY_INDEX].range.max,
+                    },
+                )
+                .with_unit(" %"),
+            }),
+            core,
+            host_to_gui_tx: Arc::new(RwLock::new(None)),
+            last_opacity: 1.0,
+        }
+    }
+}
+
+impl Plugin for BaseviewDemoNih {
+    const NAME: &'static str = "Baseview Demo";
+    const VENDOR: &'static str = "kunalarya";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "info@example.com";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+        ..AudioIOLayout::const_default()
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        Arc::clone(&self.params) as Arc<dyn Params>
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        Some(Box::new(BaseviewDemoNihEditor {
+            params: Arc::clone(&self.params),
+            core: Arc::clone(&self.core),
+            app: Arc::new(RwLock::new(None)),
+            size: Arc::new(RwLock::new((crate::WINDOW_WIDTH, crate::WINDOW_HEIGHT))),
+            host_to_gui_tx: Arc::clone(&self.host_to_gui_tx),
+        }))
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        while let Some(event) = context.next_event() {
+            if let NoteEvent::MidiCC { cc, value, .. } = event {
+                if let Some(new_gain) = self.core.apply_cc(cc, (value * 127.0).round() as u8) {
+                    self.params.gain.set_plain_value(new_gain);
+                    if let Some(tx) = &*self.host_to_gui_tx.read().unwrap() {
+                        // TODO(PANIC): replace panic with more intelligent error handling
+                        tx.send(editor_app::HostToGui::ParamUpdate(
+                            editor_app::ParamUpdate {
+                                index: plugin_core::GAIN_INDEX,
+                                value: new_gain as f64,
+                            },
+                        ))
+                        .expect("send to gui");
+                    }
+                }
+            }
+        }
+        let gain = self.params.gain.value();
+        self.core.set(plugin_core::GAIN_INDEX, gain);
+
+        // `opacity` has no `NoteEvent` path into `core` the way MIDI CC
+        // does for `gain`, so the host automating it is only observable by
+        // polling here; forward it to the editor the same way VST2's
+        // generic `set_parameter` does for every parameter.
+        let opacity = self.params.opacity.value();
+        if opacity != self.last_opacity {
+            self.last_opacity = opacity;
+            self.core.set(plugin_core::OPACITY_INDEX, opacity);
+            if let Some(tx) = &*self.host_to_gui_tx.read().unwrap() {
+                // TODO(PANIC): replace panic with more intelligent error handling
+                tx.send(editor_app::HostToGui::ParamUpdate(
+                    editor_app::ParamUpdate {
+                        index: plugin_core::OPACITY_INDEX,
+                        value: opacity as f64,
+                    },
+                ))
+                .expect("send to gui");
+            }
+        }
+
+        for channel_samples in buffer.iter_samples() {
+            for sample in channel_samples {
+                *sample = self.core.process_sample(*sample);
+            }
+        }
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for BaseviewDemoNih {
+    const CLAP_ID: &'static str = "com.kunalarya.baseview-demo";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("Bevy/baseview gain demo");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Utility];
+}
+
+impl Vst3Plugin for BaseviewDemoNih {
+    const VST3_CLASS_ID: [u8; 16] = *b"BaseviewDemoNih\0";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
+
+nih_export_clap!(BaseviewDemoNih);
+nih_export_vst3!(BaseviewDemoNih);
+
+/// Adapts the crossbeam host<->GUI channel wiring (shared with the VST2
+/// backend's `BaseviewDemoEditor`) to `nih_plug`'s `Editor` trait.
+struct BaseviewDemoNihEditor {
+    params: Arc<BaseviewDemoNihParams>,
+    /// Shared with `BaseviewDemoNih`; see its `core` field for why this
+    /// isn't just part of `params`.
+    core: Arc<ParamCore>,
+    app: Arc<RwLock<Option<bevy_baseview_plugin::AppProxy>>>,
+    /// Logical (width, height) last reported by the Bevy app, read by
+    /// `size()` and pushed to the host via `GuiContext::request_resize`.
+    size: Arc<RwLock<(f64, f64)>>,
+    /// Shared with `BaseviewDemoNih` so `process()` can also reach the
+    /// editor once it's open.
+    host_to_gui_tx: Arc<RwLock<Option<editor_app::HostToGuiTx>>>,
+}
+
+impl Editor for BaseviewDemoNihEditor {
+    fn spawn(
+        &self,
+        parent: ParentWindowHandle,
+        context: Arc<dyn GuiContext>,
+    ) -> Box<dyn std::any::Any + Send> {
+        let (logical_width, logical_height) = *self.size.read().unwrap();
+        let window_open_options = baseview::WindowOpenOptions {
+            title: "Baseview Gain Demo".to_string(),
+            size: baseview::Size::new(logical_width, logical_height),
+            scale: baseview::WindowScalePolicy::SystemScaleFactor,
+        };
+        let (host_to_gui_tx, gui_to_host_rx, app_proxy) = editor_app::create_app(
+            &window_open_options,
+            bevy_baseview_plugin::ParentWin::new(parent.raw_window_handle()),
+        );
+        for (index, _) in plugin_core::PARAMS.iter().enumerate() {
+            host_to_gui_tx
+                .send(editor_app::HostToGui::ParamUpdate(
+                    editor_app::ParamUpdate {
+                        index,
+                        value: self.core.get(index) as f64,
+                    },
+                ))
+                .expect("send to gui");
+        }
+        if let Ok(mut app_ref) = self.app.write() {
+            *app_ref = Some(app_proxy);
+        }
+        if let Ok(mut tx_ref) = self.host_to_gui_tx.write() {
+            *tx_ref = Some(host_to_gui_tx.clone());
+        }
+
+        // Relay every `GuiToHost` variant for as long as the window stays
+        // open, mirroring what `BaseviewDemo::process_gui_msgs` does for
+        // VST2 (there, these same variants drive `self.host.automate`,
+        // `self.core.toggle_midi_learn`, etc.).
+        let size = Arc::clone(&self.size);
+        let params = Arc::clone(&self.params);
+        let core = Arc::clone(&self.core);
+        std::thread::spawn(move || {
+            for msg in gui_to_host_rx.iter() {
+                match msg {
+                    editor_app::GuiToHost::ParamUpdate(update) => {
+                        core.set(update.index, update.value as f32);
+                        if let Some(param_ptr) = params.param_ptr(update.index) {
+                            // `raw_set_parameter_normalized` wants a
+                            // normalized value, same as VST2's
+                            // `Host::automate`; `update.value` is real.
+                            let normalized = plugin_core::PARAMS[update.index]
+                                .range
+                                .normalize(update.value as f32);
+                            // SAFETY: `param_ptr` comes from `self.params`,
+                            // the same `Params` instance this `GuiContext`
+                            // was handed for.
+                            unsafe {
+                                context.raw_begin_set_parameter(param_ptr);
+                                context.raw_set_parameter_normalized(param_ptr, normalized);
+                                context.raw_end_set_parameter(param_ptr);
+                            }
+                        }
+                    }
+                    editor_app::GuiToHost::MidiLearnToggle => core.toggle_midi_learn(),
+                    editor_app::GuiToHost::ScaleFactorChanged(_) => {
+                        // Unlike VST2 (which needs `window_info` reconciled
+                        // so `Editor::size` stays accurate for polling),
+                        // nih_plug negotiates scale the other way around
+                        // (host -> plugin via `Editor::set_scale_factor`),
+                        // and `GuiContext` has no push for it, so there's
+                        // nothing to forward here.
+                    }
+                    editor_app::GuiToHost::SizeChanged(width, height) => {
+                        if let Ok(mut size) = size.write() {
+                            *size = (width, height);
+                        }
+                        context.request_resize();
+                    }
+                }
+            }
+        });
+
+        Box::new(Arc::clone(&self.app))
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let (width, height) = *self.size.read().unwrap();
+        (width as u32, height as u32)
+    }
+
+    fn set_scale_factor(&self, _factor: f32) -> bool {
+        false
+    }
+
+    fn param_value_changed(&self, _id: &str, _normalized_value: f32) {}
+    fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {}
+    fn param_values_changed(&self) {}
+}
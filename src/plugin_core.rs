@@ -0,0 +1,286 @@
+//! Framework-agnostic plugin core.
+//!
+//! This holds the DSP state and parameter data shared by every host backend
+//! (VST2 in `lib.rs`, VST3/CLAP in `nih_backend.rs`). Nothing in this module
+//! knows about `vst::Plugin`, `nih_plug::Plugin`, baseview, or bevy, so it
+//! can be reused unchanged as more backends are added.
+//!
+//! Parameters are described declaratively in [`PARAMS`] and stored in a
+//! [`ParamCore`] indexed by position: the shared storage, DSP, and MIDI
+//! learn logic in this module only need the table updated to pick up a new
+//! parameter. `nih_plug`'s `#[derive(Params)]` still needs one named
+//! `FloatParam` field per parameter in `nih_backend.rs` (the macro requires
+//! it), so that backend's struct, its `Default` impl, and `param_ptr` match
+//! still need a line added too.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::RwLock;
+
+/// Lock-free f32 storage, since `std::sync::atomic` has no native float
+/// type. Bit-compatible replacement for `vst::util::AtomicFloat` so this
+/// module doesn't have to depend on the `vst` crate.
+pub struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    pub fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed)
+    }
+}
+
+/// A parameter's value range in "real" units -- what `ParamCore`, DSP code,
+/// and `nih_plug`'s `FloatParam` all work in. VST2's `PluginParameters` is
+/// the odd one out: it's confined to normalized `0.0..=1.0`, so `lib.rs`
+/// uses [`normalize`](ParamRange::normalize)/[`denormalize`](ParamRange::denormalize)
+/// to convert at that boundary.
+#[derive(Clone, Copy)]
+pub struct ParamRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ParamRange {
+    pub const UNIT: ParamRange = ParamRange { min: 0.0, max: 1.0 };
+
+    pub fn normalize(&self, value: f32) -> f32 {
+        if self.max == self.min {
+            0.0
+        } else {
+            (value - self.min) / (self.max - self.min)
+        }
+    }
+
+    pub fn denormalize(&self, normalized: f32) -> f32 {
+        self.min + normalized * (self.max - self.min)
+    }
+}
+
+/// A single parameter's static metadata: display name/unit, real-world
+/// range, default, and a value-to-text formatter. A second parameter (e.g.
+/// filter cutoff in Hz) is added by adding an entry here, plus the
+/// `nih_backend.rs` field described in the module docs.
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub range: ParamRange,
+    pub default: f32,
+    pub format: fn(f32) -> String,
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Finds a parameter's position in [`PARAMS`] by name, so `GAIN_INDEX`/
+/// `OPACITY_INDEX` stay correct if the table is ever reordered instead of
+/// having to be hand-renumbered along with it.
+const fn index_of(name: &str) -> usize {
+    let mut i = 0;
+    while i < PARAMS.len() {
+        if str_eq(PARAMS[i].name, name) {
+            return i;
+        }
+        i += 1;
+    }
+    panic!("no such parameter in PARAMS")
+}
+
+/// The gain parameter: index into [`PARAMS`]/[`ParamCore`] and the only
+/// parameter the hardware MIDI learn in `apply_cc` can bind to.
+pub const GAIN_INDEX: usize = index_of("gain");
+/// The editor background opacity parameter; see its entry in [`PARAMS`]
+/// for the "cosmetic only" caveat.
+pub const OPACITY_INDEX: usize = index_of("opacity");
+
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "gain",
+        label: "%",
+        range: ParamRange::UNIT,
+        default: 1.0,
+        format: |value| format!("{:.1} dB", 20.0 * value.log10()),
+    },
+    // Cosmetic only: sets the alpha of Bevy's `ClearColor` and background
+    // sprite. The editor window is not opened with an alpha-capable surface,
+    // so whether the host shows through depends on the platform compositor.
+    ParamSpec {
+        name: "opacity",
+        label: "%",
+        range: ParamRange::UNIT,
+        default: 1.0,
+        format: |value| format!("{:.0}%", value * 100.0),
+    },
+];
+
+/// CC -> parameter mapping, plus whether we're currently waiting on the
+/// next incoming CC to bind it ("MIDI learn"). MIDI learn always targets
+/// [`GAIN_INDEX`]; there's no UI yet to pick a different target parameter.
+#[derive(Default)]
+pub struct MidiLearnState {
+    pub learning: bool,
+    pub mapped_cc: Option<u8>,
+}
+
+/// Every parameter in [`PARAMS`], stored by index, plus the MIDI learn
+/// bookkeeping for them. Every host backend wraps this in whatever
+/// parameter trait it needs and forwards `get`/`set`/CC handling into it so
+/// the DSP and the MIDI learn logic only has to be written once.
+pub struct ParamCore {
+    values: Vec<AtomicF32>,
+    pub midi_map: RwLock<MidiLearnState>,
+}
+
+impl ParamCore {
+    pub fn new() -> Self {
+        Self {
+            values: PARAMS
+                .iter()
+                .map(|spec| AtomicF32::new(spec.default))
+                .collect(),
+            midi_map: RwLock::new(MidiLearnState::default()),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> f32 {
+        self.values[index].get()
+    }
+
+    pub fn set(&self, index: usize, value: f32) {
+        self.values[index].set(value)
+    }
+
+    pub fn text(&self, index: usize) -> String {
+        (PARAMS[index].format)(self.get(index))
+    }
+
+    /// Apply the current gain to one sample. Shared by every backend's
+    /// process callback.
+    pub fn process_sample(&self, input: f32) -> f32 {
+        input * self.get(GAIN_INDEX)
+    }
+
+    pub fn toggle_midi_learn(&self) {
+        match self.midi_map.write() {
+            Ok(mut midi_map) => midi_map.learning = !midi_map.learning,
+            Err(err) => log::error!("Unable to write midi_map: {err:?}"),
+        }
+    }
+
+    /// Feed a CC number/value pair through MIDI learn and, once a CC is
+    /// mapped, into the gain parameter. Returns the new gain when it was
+    /// updated, so the caller can notify the host/GUI.
+    pub fn apply_cc(&self, cc_number: u8, cc_value: u8) -> Option<f32> {
+        let mapped_now = {
+            let mut midi_map = match self.midi_map.write() {
+                Ok(midi_map) => midi_map,
+                Err(err) => {
+                    log::error!("Unable to write midi_map: {err:?}");
+                    return None;
+                }
+            };
+            if midi_map.learning {
+                midi_map.learning = false;
+                midi_map.mapped_cc = Some(cc_number);
+                log::info!("MIDI learn: mapped CC {cc_number} to gain");
+                return None;
+            }
+            midi_map.mapped_cc == Some(cc_number)
+        };
+        if !mapped_now {
+            return None;
+        }
+        let gain = cc_value as f32 / 127.0;
+        self.set(GAIN_INDEX, gain);
+        Some(gain)
+    }
+}
+
+impl Default for ParamCore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_and_opacity_have_distinct_indices() {
+        assert_ne!(GAIN_INDEX, OPACITY_INDEX);
+        assert_eq!(PARAMS[GAIN_INDEX].name, "gain");
+        assert_eq!(PARAMS[OPACITY_INDEX].name, "opacity");
+    }
+
+    #[test]
+    fn cc_is_ignored_until_learned() {
+        let core = ParamCore::new();
+        assert_eq!(core.apply_cc(7, 64), None);
+        assert_eq!(core.get(GAIN_INDEX), PARAMS[GAIN_INDEX].default);
+    }
+
+    #[test]
+    fn learning_maps_the_next_cc_without_applying_it() {
+        let core = ParamCore::new();
+        core.toggle_midi_learn();
+        assert_eq!(core.apply_cc(7, 0), None);
+        assert_eq!(core.get(GAIN_INDEX), PARAMS[GAIN_INDEX].default);
+        assert_eq!(core.midi_map.read().unwrap().mapped_cc, Some(7));
+        assert!(!core.midi_map.read().unwrap().learning);
+    }
+
+    #[test]
+    fn mapped_cc_updates_gain_and_unmapped_ccs_are_gated() {
+        let core = ParamCore::new();
+        core.toggle_midi_learn();
+        core.apply_cc(7, 0); // learn CC 7
+
+        assert_eq!(core.apply_cc(8, 127), None); // different CC, ignored
+        assert_eq!(core.get(GAIN_INDEX), PARAMS[GAIN_INDEX].default);
+
+        assert_eq!(core.apply_cc(7, 127), Some(1.0));
+        assert_eq!(core.get(GAIN_INDEX), 1.0);
+    }
+
+    #[test]
+    fn toggle_midi_learn_flips_the_flag() {
+        let core = ParamCore::new();
+        assert!(!core.midi_map.read().unwrap().learning);
+        core.toggle_midi_learn();
+        assert!(core.midi_map.read().unwrap().learning);
+        core.toggle_midi_learn();
+        assert!(!core.midi_map.read().unwrap().learning);
+    }
+
+    #[test]
+    fn param_range_normalize_and_denormalize_round_trip() {
+        let range = ParamRange {
+            min: 20.0,
+            max: 120.0,
+        };
+        assert_eq!(range.denormalize(0.0), 20.0);
+        assert_eq!(range.denormalize(1.0), 120.0);
+        assert_eq!(range.normalize(20.0), 0.0);
+        assert_eq!(range.normalize(120.0), 1.0);
+        assert_eq!(range.normalize(range.denormalize(0.25)), 0.25);
+    }
+}